@@ -4,12 +4,23 @@
 //! a vector u64
 
 use alloc::vec::Vec;
+use core::cell::Cell;
 
 #[macro_use]
 extern crate alloc;
 
 
 const MIN_BITMAP_SIZE: usize  = 64;
+
+/// Errors returned while reconstructing a `Bitmap` from a byte buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapError {
+    /// `bytes.len()` did not match the 16-byte header plus the word count
+    /// implied by the requested size
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+#[derive(Clone)]
 pub struct Bitmap {
 
     rounded_size: usize,
@@ -21,8 +32,16 @@ pub struct Bitmap {
 
     /// This is the start-bit within the Bitmap
     /// The space before this bit is skipped
-    /// by the `find_free_slot` function 
-    start_bit   : usize,    
+    /// by the `find_free_slot` function
+    start_bit   : usize,
+
+    /// Lowest bit index that could possibly be free. `find_free_slot`
+    /// advances this past words it scans and finds fully allocated,
+    /// `unset_bit` and the other operations that clear bits lower it, so
+    /// repeated scans skip already-known-full words.
+    /// A `Cell` so `find_free_slot` can keep its pre-existing `&self`
+    /// signature instead of forcing every caller over to `&mut self`
+    none_free_before: Cell<usize>,
 }
 
 impl Bitmap {
@@ -65,8 +84,9 @@ impl Bitmap {
             mask:         mask, 
             log_sz:       log_sz,    
             start_bit:    0,
+            none_free_before: Cell::new(0),
             bitmap:       vec![0u64; bitmap_size]
-        }        
+        }
     }
 
     pub fn new_with_reserved(size: usize, reserved_space: usize) -> Self {
@@ -74,6 +94,7 @@ impl Bitmap {
 
         let mut instance = Self::new(size);
         instance.start_bit = reserved_space;
+        instance.none_free_before = Cell::new(reserved_space);
         instance
     }
 
@@ -93,6 +114,8 @@ impl Bitmap {
         let mask_bit        = bit & self.mask;
 
         self.bitmap[selected_mask] &= !(1u64 << mask_bit);
+
+        self.none_free_before.set(core::cmp::min(self.none_free_before.get(), bit));
     }
 
     pub fn is_set(&self, bit: usize) -> bool {
@@ -104,20 +127,269 @@ impl Bitmap {
         ((self.bitmap[selected_mask] >> mask_bit) & 1) == 1
     }
 
-    /// Finds the first unused bit in the bitmap
+    /// Sets every bit in the half-open range `[start, end)` using
+    /// whole-word operations instead of a bit-by-bit loop
+    pub fn set_range(&mut self, start: usize, end: usize) {
+        assert!(start <= end);
+        assert!(end <= self.rounded_size);
+
+        if start == end {
+            return;
+        }
+
+        let first_word = start >> self.log_sz;
+        let last_word  = (end - 1) >> self.log_sz;
+
+        if first_word == last_word {
+            let range_mask = if end - start == 64 {
+                !0u64
+            } else {
+                ((1u64 << (end - start)) - 1) << (start & self.mask)
+            };
+            self.bitmap[first_word] |= range_mask;
+            return;
+        }
+
+        // Leading partial word: set the high bits from `start` onward
+        self.bitmap[first_word] |= !0u64 << (start & self.mask);
+
+        // Fully-covered interior words
+        for word in &mut self.bitmap[first_word + 1..last_word] {
+            *word = !0u64;
+        }
+
+        // Trailing partial word: set the low bits up to `end`
+        let end_bit = end & self.mask;
+        let tail_mask = if end_bit == 0 { !0u64 } else { (1u64 << end_bit) - 1 };
+        self.bitmap[last_word] |= tail_mask;
+    }
+
+    /// Clears every bit in the half-open range `[start, end)` using
+    /// whole-word operations instead of a bit-by-bit loop
+    pub fn clear_range(&mut self, start: usize, end: usize) {
+        assert!(start <= end);
+        assert!(end <= self.rounded_size);
+
+        if start == end {
+            return;
+        }
+
+        self.none_free_before.set(core::cmp::min(self.none_free_before.get(), start));
+
+        let first_word = start >> self.log_sz;
+        let last_word  = (end - 1) >> self.log_sz;
+
+        if first_word == last_word {
+            let range_mask = if end - start == 64 {
+                !0u64
+            } else {
+                ((1u64 << (end - start)) - 1) << (start & self.mask)
+            };
+            self.bitmap[first_word] &= !range_mask;
+            return;
+        }
+
+        // Leading partial word: clear the high bits from `start` onward
+        self.bitmap[first_word] &= !(!0u64 << (start & self.mask));
+
+        // Fully-covered interior words
+        for word in &mut self.bitmap[first_word + 1..last_word] {
+            *word = 0u64;
+        }
+
+        // Trailing partial word: clear the low bits up to `end`
+        let end_bit = end & self.mask;
+        let tail_mask = if end_bit == 0 { !0u64 } else { (1u64 << end_bit) - 1 };
+        self.bitmap[last_word] &= !tail_mask;
+    }
+
+    /// Finds the first unused bit in the bitmap, scanning a whole `u64`
+    /// word at a time instead of bit-by-bit
     pub fn find_free_slot(&self) -> Option<usize> {
-        for bit in self.start_bit..self.rounded_size {
-            let selected_mask   = bit >> self.log_sz;
-            let mask_bit        = bit & self.mask;
+        let start_bit   = core::cmp::max(self.start_bit, self.none_free_before.get());
+        let first_word  = start_bit >> self.log_sz;
+
+        for word_index in first_word..self.bitmap.len() {
+            let word = self.bitmap[word_index];
+
+            // Fully allocated word, skip it. As long as the hint lies in
+            // this word (every word scanned from the hint on was full),
+            // advance the hint past it
+            if word == u64::MAX {
+                if self.none_free_before.get() >> self.log_sz == word_index {
+                    self.none_free_before.set((word_index + 1) << self.log_sz);
+                }
+                continue;
+            }
+
+            // On the first word, mask off bits below `start_bit` so they
+            // aren't reported as free
+            let word = if word_index == first_word {
+                word | ((1u64 << (start_bit & self.mask)) - 1)
+            } else {
+                word
+            };
+
+            if word != u64::MAX {
+                let offset = word.trailing_ones() as usize;
+                return Some(word_index * 64 + offset);
+            }
+        }
+        None
+    }
+
+    /// Finds the start bit of the first run of `count` consecutive clear
+    /// bits at or after `start_bit`. Scans word-by-word, jumping over
+    /// fully-free or fully-allocated words in one step. Within a mixed
+    /// word each run of free bits is crossed in one step via
+    /// `trailing_zeros`; set bits are stepped over one at a time
+    pub fn find_free_run(&self, count: usize) -> Option<usize> {
+        if count == 0 {
+            return Some(self.start_bit);
+        }
+
+        let mut run_len   = 0usize;
+        let mut run_start = self.start_bit;
+        let first_word    = self.start_bit >> self.log_sz;
+
+        for word_index in first_word..self.bitmap.len() {
+            let mut word = self.bitmap[word_index];
+            if word_index == first_word {
+                let start_in_word = self.start_bit & self.mask;
+                if start_in_word != 0 {
+                    word |= (1u64 << start_in_word) - 1;
+                }
+            }
+
+            if word == 0 {
+                // Fully free word: extend the run across all 64 bits at once
+                if run_len == 0 {
+                    run_start = word_index << self.log_sz;
+                }
+                run_len += 64;
+                if run_len >= count {
+                    return Some(run_start);
+                }
+                continue;
+            }
 
-            // If the bit is not set, return it
-            if ((self.bitmap[selected_mask] >> mask_bit) & 1) == 0 {
-                return Some(bit);
+            if word == u64::MAX {
+                // Fully allocated word: any run in progress is broken
+                run_len = 0;
+                continue;
+            }
+
+            // Mixed word: cross each free-bit run in one step using
+            // trailing_zeros, resetting the run whenever we land on a set bit
+            let mut bit = 0usize;
+            while bit < 64 {
+                let remaining = word >> bit;
+                if remaining == 0 {
+                    if run_len == 0 {
+                        run_start = (word_index << self.log_sz) + bit;
+                    }
+                    run_len += 64 - bit;
+                    if run_len >= count {
+                        return Some(run_start);
+                    }
+                    break;
+                }
+
+                let free_here = remaining.trailing_zeros() as usize;
+                if free_here > 0 {
+                    if run_len == 0 {
+                        run_start = (word_index << self.log_sz) + bit;
+                    }
+                    run_len += free_here;
+                    if run_len >= count {
+                        return Some(run_start);
+                    }
+                }
+                run_len = 0;
+                bit += free_here + 1;
             }
         }
         None
     }
 
+    /// Finds a run of `count` consecutive clear bits and sets it atomically,
+    /// returning its start bit
+    pub fn allocate_run(&mut self, count: usize) -> Option<usize> {
+        let start = self.find_free_run(count)?;
+        self.set_range(start, start + count);
+        Some(start)
+    }
+
+    /// Returns the number of set bits. `rounded_size` is always a multiple
+    /// of 64 so there are no trailing padding bits to exclude
+    pub fn count_ones(&self) -> usize {
+        self.bitmap.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns the number of clear bits
+    pub fn count_zeros(&self) -> usize {
+        self.bitmap.iter().map(|word| word.count_zeros() as usize).sum()
+    }
+
+    /// Returns an iterator over the index of every set bit, in ascending order
+    pub fn iter_set_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bitmap.iter().enumerate().flat_map(|(word_index, &word)| {
+            let mut word = word;
+            core::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let tz = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(word_index * 64 + tz)
+            })
+        })
+    }
+
+    /// Grows the bitmap to cover at least `new_size` bits, rounded up to the
+    /// next power of two. New bits default clear. Existing contents and the
+    /// positions of already-set bits are preserved
+    pub fn grow(&mut self, new_size: usize) {
+        let new_size = core::cmp::max(new_size, MIN_BITMAP_SIZE);
+        let rounded_size = Bitmap::roundup_pow_of_two(new_size);
+        assert!(rounded_size >= self.rounded_size);
+
+        let new_word_count = rounded_size >> self.log_sz;
+        self.bitmap.resize(new_word_count, 0u64);
+        self.rounded_size = rounded_size;
+    }
+
+    /// Shrinks the bitmap to `new_size` bits, truncating the backing store
+    /// and clearing any now-out-of-range bits left over in the last
+    /// retained word. `rounded_size` is rounded up to the word it keeps,
+    /// just like the backing store, so it never claims bits narrower than
+    /// what is actually retained
+    pub fn shrink_to(&mut self, new_size: usize) {
+        assert!(new_size <= self.rounded_size);
+
+        let mut new_word_count = new_size >> self.log_sz;
+        if (new_size & self.mask) != 0 {
+            new_word_count += 1;
+        }
+        self.bitmap.truncate(new_word_count);
+
+        let valid_bits = new_size & self.mask;
+        if valid_bits != 0 {
+            let keep_mask = (1u64 << valid_bits) - 1;
+            let last_word = new_word_count - 1;
+            self.bitmap[last_word] &= keep_mask;
+        }
+
+        self.rounded_size = new_word_count << self.log_sz;
+
+        // A reservation or scan hint past the new rounded_size would make
+        // find_free_slot/find_free_run start scanning past the end of the
+        // (now shorter) backing store. The hint is also pulled back to
+        // `new_size`, since the bits cleared from there on are free again
+        self.start_bit = core::cmp::min(self.start_bit, self.rounded_size);
+        self.none_free_before.set(core::cmp::min(self.none_free_before.get(), new_size));
+    }
+
     /// Returns the space of the bitmap in bits
     pub fn bit_size(&self) -> usize {
         self.rounded_size
@@ -127,6 +399,191 @@ impl Bitmap {
     pub fn size(&self) -> usize {
         self.bitmap.len()
     }
+
+    /// Serializes the bitmap to a byte buffer, fixed little-endian: a 16-byte
+    /// header (`start_bit`, then `none_free_before`, each as a `u64`)
+    /// followed by the backing words, one `u64` per 8 bytes. Round-trips
+    /// through `try_from_bytes` when `bit_size()` is a power of two; after a
+    /// `shrink_to` that leaves a `bit_size()` that is not a power of two,
+    /// `try_from_bytes` rejects the buffer for every `size`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.bitmap.len() * 8);
+        bytes.extend_from_slice(&(self.start_bit as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.none_free_before.get() as u64).to_le_bytes());
+        for word in &self.bitmap {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reconstructs a `Bitmap` of `size` bits from a buffer produced by
+    /// `to_bytes`. Fails if `bytes` isn't exactly the word count implied
+    /// by `size`'s rounded-up length, plus the fixed 16-byte
+    /// `start_bit`/`none_free_before` header
+    pub fn try_from_bytes(bytes: &[u8], size: usize) -> Result<Self, BitmapError> {
+        let size = if size < MIN_BITMAP_SIZE {
+            MIN_BITMAP_SIZE
+        } else {
+            size
+        };
+
+        let rounded_size = Bitmap::roundup_pow_of_two(size);
+
+        let log_sz: usize = 6;
+        let mask: usize   = 0x3f;
+
+        let mut word_count = rounded_size >> log_sz;
+        if (rounded_size & mask) != 0 {
+            word_count += 1;
+        }
+
+        let expected_len = 16 + word_count * 8;
+        if bytes.len() != expected_len {
+            return Err(BitmapError::LengthMismatch { expected: expected_len, actual: bytes.len() });
+        }
+
+        let start_bit         = u64::from_le_bytes(bytes[0..8].try_into().unwrap()) as usize;
+        let none_free_before   = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+
+        let bitmap = bytes[16..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        Ok(Self {
+            rounded_size: rounded_size,
+            mask:         mask,
+            log_sz:       log_sz,
+            start_bit:    start_bit,
+            none_free_before: Cell::new(none_free_before),
+            bitmap:       bitmap,
+        })
+    }
+
+    /// Returns a new `Bitmap` holding the bitwise AND of `self` and `other`.
+    /// Both bitmaps must share the same `rounded_size`
+    pub fn and(&self, other: &Bitmap) -> Bitmap {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        let mut result = self.clone();
+        result.and_assign(other);
+        result
+    }
+
+    /// Returns a new `Bitmap` holding the bitwise OR of `self` and `other`.
+    /// Both bitmaps must share the same `rounded_size`
+    pub fn or(&self, other: &Bitmap) -> Bitmap {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        let mut result = self.clone();
+        result.or_assign(other);
+        result
+    }
+
+    /// Returns a new `Bitmap` holding the bitwise XOR of `self` and `other`.
+    /// Both bitmaps must share the same `rounded_size`
+    pub fn xor(&self, other: &Bitmap) -> Bitmap {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        let mut result = self.clone();
+        result.xor_assign(other);
+        result
+    }
+
+    /// ANDs `other` into `self` in place
+    pub fn and_assign(&mut self, other: &Bitmap) {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        for (word, other_word) in self.bitmap.iter_mut().zip(other.bitmap.iter()) {
+            *word &= *other_word;
+        }
+        self.none_free_before.set(0);
+    }
+
+    /// ORs `other` into `self` in place
+    pub fn or_assign(&mut self, other: &Bitmap) {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        for (word, other_word) in self.bitmap.iter_mut().zip(other.bitmap.iter()) {
+            *word |= *other_word;
+        }
+    }
+
+    /// XORs `other` into `self` in place
+    pub fn xor_assign(&mut self, other: &Bitmap) {
+        assert_eq!(self.rounded_size, other.rounded_size);
+
+        for (word, other_word) in self.bitmap.iter_mut().zip(other.bitmap.iter()) {
+            *word ^= *other_word;
+        }
+        self.none_free_before.set(0);
+    }
+
+    /// Returns a new `Bitmap` with every bit flipped. Since `rounded_size`
+    /// is always a multiple of 64 there are no trailing padding bits to mask
+    pub fn not(&self) -> Bitmap {
+        let mut result = self.clone();
+        result.invert();
+        result
+    }
+
+    /// Flips every bit of `self` in place
+    pub fn invert(&mut self) {
+        for word in &mut self.bitmap {
+            *word = !*word;
+        }
+        self.none_free_before.set(0);
+    }
+}
+
+impl core::ops::BitAnd for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitand(self, other: &Bitmap) -> Bitmap {
+        self.and(other)
+    }
+}
+
+impl core::ops::BitOr for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitor(self, other: &Bitmap) -> Bitmap {
+        self.or(other)
+    }
+}
+
+impl core::ops::BitXor for &Bitmap {
+    type Output = Bitmap;
+
+    fn bitxor(self, other: &Bitmap) -> Bitmap {
+        self.xor(other)
+    }
+}
+
+impl core::ops::Not for &Bitmap {
+    type Output = Bitmap;
+
+    fn not(self) -> Bitmap {
+        Bitmap::not(self)
+    }
+}
+
+impl core::ops::BitAndAssign<&Bitmap> for Bitmap {
+    fn bitand_assign(&mut self, other: &Bitmap) {
+        self.and_assign(other);
+    }
+}
+
+impl core::ops::BitOrAssign<&Bitmap> for Bitmap {
+    fn bitor_assign(&mut self, other: &Bitmap) {
+        self.or_assign(other);
+    }
+}
+
+impl core::ops::BitXorAssign<&Bitmap> for Bitmap {
+    fn bitxor_assign(&mut self, other: &Bitmap) {
+        self.xor_assign(other);
+    }
 }
 
 
@@ -189,4 +646,220 @@ mod tests {
         bitmap.unset_bit(0);
         assert_eq!(bitmap.find_free_slot(), Some(0usize));
     }
+
+    /// Test set_range / clear_range within a single word
+    #[test]
+    fn test_bitmap6() {
+        let mut bitmap = Bitmap::new(64);
+
+        bitmap.set_range(4, 8);
+        for bit in 0..64 {
+            assert_eq!(bitmap.is_set(bit), (4..8).contains(&bit));
+        }
+
+        bitmap.clear_range(5, 7);
+        for bit in 0..64 {
+            assert_eq!(bitmap.is_set(bit), bit == 4 || bit == 7);
+        }
+    }
+
+    /// Test set_range / clear_range spanning multiple words
+    #[test]
+    fn test_bitmap7() {
+        let mut bitmap = Bitmap::new(192);
+
+        bitmap.set_range(10, 150);
+        for bit in 0..192 {
+            assert_eq!(bitmap.is_set(bit), (10..150).contains(&bit));
+        }
+
+        bitmap.clear_range(64, 128);
+        for bit in 0..192 {
+            assert_eq!(bitmap.is_set(bit), (10..64).contains(&bit) || (128..150).contains(&bit));
+        }
+    }
+
+    /// Test the and / or / xor / not combinators
+    #[test]
+    fn test_bitmap8() {
+        let mut a = Bitmap::new(64);
+        let mut b = Bitmap::new(64);
+
+        a.set_range(0, 8);
+        b.set_range(4, 12);
+
+        let and = a.and(&b);
+        let or  = a.or(&b);
+        let xor = a.xor(&b);
+
+        for bit in 0..64 {
+            assert_eq!(and.is_set(bit), (4..8).contains(&bit));
+            assert_eq!(or.is_set(bit), (0..12).contains(&bit));
+            assert_eq!(xor.is_set(bit), (0..4).contains(&bit) || (8..12).contains(&bit));
+        }
+
+        let not_a = a.not();
+        for bit in 0..64 {
+            assert_eq!(not_a.is_set(bit), !(0..8).contains(&bit));
+        }
+
+        a.and_assign(&b);
+        for bit in 0..64 {
+            assert_eq!(a.is_set(bit), (4..8).contains(&bit));
+        }
+    }
+
+    /// Test that find_free_slot skips fully-allocated words and that
+    /// unset_bit lowers the none_free_before hint again
+    #[test]
+    fn test_bitmap9() {
+        let mut bitmap = Bitmap::new(192);
+
+        bitmap.set_range(0, 128);
+        assert_eq!(bitmap.find_free_slot(), Some(128usize));
+
+        bitmap.unset_bit(10);
+        assert_eq!(bitmap.find_free_slot(), Some(10usize));
+    }
+
+    /// Test find_free_run / allocate_run across word boundaries
+    #[test]
+    fn test_bitmap10() {
+        let mut bitmap = Bitmap::new(192);
+
+        bitmap.set_range(0, 70);
+        assert_eq!(bitmap.find_free_run(10), Some(70usize));
+
+        let run = bitmap.allocate_run(58);
+        assert_eq!(run, Some(70usize));
+        for bit in 0..128 {
+            assert!(bitmap.is_set(bit));
+        }
+
+        assert_eq!(bitmap.find_free_run(1), Some(128usize));
+        assert_eq!(bitmap.find_free_run(bitmap.bit_size() - 127), None);
+    }
+
+    /// Test that a zero-length run is trivially satisfied even when the
+    /// bit at start_bit is already set
+    #[test]
+    fn test_bitmap10b() {
+        let mut bitmap = Bitmap::new(64);
+        bitmap.set_bit(0);
+
+        assert_eq!(bitmap.find_free_run(0), Some(0usize));
+    }
+
+    /// Test count_ones / count_zeros / iter_set_bits
+    #[test]
+    fn test_bitmap11() {
+        let mut bitmap = Bitmap::new(128);
+
+        bitmap.set_bit(0);
+        bitmap.set_bit(63);
+        bitmap.set_bit(64);
+        bitmap.set_bit(100);
+
+        assert_eq!(bitmap.count_ones(), 4);
+        assert_eq!(bitmap.count_zeros(), 124);
+
+        let set_bits: Vec<usize> = bitmap.iter_set_bits().collect();
+        assert_eq!(set_bits, vec![0usize, 63, 64, 100]);
+    }
+
+    /// Test grow / shrink_to preserve contents and clear out-of-range bits
+    #[test]
+    fn test_bitmap12() {
+        let mut bitmap = Bitmap::new(64);
+        bitmap.set_bit(10);
+        bitmap.set_bit(63);
+
+        bitmap.grow(200);
+        assert_eq!(bitmap.bit_size(), 256);
+        assert_eq!(bitmap.size(), 4);
+        assert!(bitmap.is_set(10));
+        assert!(bitmap.is_set(63));
+        assert_eq!(bitmap.count_ones(), 2);
+
+        bitmap.set_bit(150);
+        bitmap.shrink_to(100);
+
+        // rounded_size rounds up to the word the backing store actually
+        // retains, so it never claims bits narrower than the real size
+        assert_eq!(bitmap.bit_size(), 128);
+        assert_eq!(bitmap.size(), 2);
+        assert!(bitmap.is_set(10));
+        assert!(bitmap.is_set(63));
+        assert_eq!(bitmap.count_ones(), 2);
+        assert_eq!(bitmap.count_zeros(), 126);
+
+        // Bits 100-127 are beyond the requested 100 but still inside the
+        // retained word, so they read back as ordinary free bits rather
+        // than being invisible to find_free_slot/set_bit
+        assert_eq!(bitmap.find_free_slot(), Some(0usize));
+        bitmap.set_bit(100);
+        assert!(bitmap.is_set(100));
+    }
+
+    /// Test that shrinking below a reservation/scan hint clamps both so
+    /// find_free_slot doesn't start scanning past the shrunk backing store
+    #[test]
+    fn test_bitmap12b() {
+        // The reservation is entirely swallowed by the shrink: nothing left
+        // to scan, but find_free_slot must stay well-behaved (no panic, and
+        // stable across repeated calls) instead of chasing a stale start_bit
+        // that no longer fits the new rounded_size
+        let mut bitmap = Bitmap::new_with_reserved(256, 200);
+        bitmap.shrink_to(64);
+        assert_eq!(bitmap.bit_size(), 64);
+        assert_eq!(bitmap.find_free_slot(), None);
+        assert_eq!(bitmap.find_free_slot(), None);
+
+        // A reservation that still fits after the shrink keeps working
+        let mut reserved = Bitmap::new_with_reserved(256, 20);
+        reserved.shrink_to(128);
+        assert_eq!(reserved.bit_size(), 128);
+        assert_eq!(reserved.find_free_slot(), Some(20usize));
+    }
+
+    /// Test round-tripping a Bitmap through to_bytes / try_from_bytes
+    #[test]
+    fn test_bitmap13() {
+        let mut bitmap = Bitmap::new(128);
+        bitmap.set_bit(10);
+        bitmap.set_bit(100);
+
+        let bytes = bitmap.to_bytes();
+        assert_eq!(bytes.len(), 32);
+
+        let restored = Bitmap::try_from_bytes(&bytes, 128).unwrap();
+        assert_eq!(restored.bit_size(), 128);
+        assert!(restored.is_set(10));
+        assert!(restored.is_set(100));
+        assert_eq!(restored.count_ones(), 2);
+    }
+
+    /// Test that try_from_bytes rejects a buffer of the wrong length
+    #[test]
+    fn test_bitmap14() {
+        let bytes = [0u8; 8];
+        match Bitmap::try_from_bytes(&bytes, 128) {
+            Err(err) => assert_eq!(err, BitmapError::LengthMismatch { expected: 32, actual: 8 }),
+            Ok(_) => panic!("expected a length mismatch error"),
+        }
+    }
+
+    /// Test that a reservation made via new_with_reserved survives a
+    /// to_bytes/try_from_bytes round-trip, so find_free_slot can't hand out
+    /// bits in what was supposed to be permanently-reserved space
+    #[test]
+    fn test_bitmap15() {
+        let bitmap = Bitmap::new_with_reserved(128, 20);
+
+        let bytes = bitmap.to_bytes();
+        let restored = Bitmap::try_from_bytes(&bytes, 128).unwrap();
+
+        assert_eq!(restored.find_free_slot(), Some(20usize));
+        assert_eq!(restored.find_free_slot(), bitmap.find_free_slot());
+    }
 }